@@ -1,11 +1,38 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::Duration;
+
 use clap::Parser;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use lofty::{Accessor, Tag};
 use log::{debug, error, info, warn};
 use rspotify::clients::{BaseClient, OAuthClient};
-use rspotify::model::{PlayableId, SearchResult, SearchType, TrackId};
-use rspotify::{scopes, AuthCodeSpotify, ClientResult, Credentials, OAuth};
+use rspotify::http::HttpError;
+use rspotify::model::{
+    AlbumId, FullPlaylist, FullTrack, PlayableId, PlayableItem, PlaylistId, SearchResult,
+    SearchType, TrackId,
+};
+use rspotify::{scopes, AuthCodeSpotify, ClientError, ClientResult, Config, Credentials, OAuth};
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
+/// Fallback sleep when Spotify rate-limits us without a `Retry-After` header.
+const DEFAULT_RETRY_AFTER_SECS: u64 = 5;
+
+/// How many consecutive hard errors to tolerate before giving up.
+const MAX_HARD_RETRIES: u32 = 5;
+
+/// How many candidates to request per search so the matcher has a choice.
+const CANDIDATES_PER_QUERY: u32 = 5;
+
+/// Minimum combined score (0..=1) a candidate must reach to count as a match.
+const MATCH_THRESHOLD: f64 = 0.5;
+
+/// Minimum artist similarity (0..=1) below which a candidate is rejected
+/// outright, so a same-title cover by a different artist cannot match.
+const MIN_ARTIST_SIMILARITY: f64 = 0.5;
+
 /// Simple program to import your local music library to Spotify
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -21,21 +48,192 @@ struct Args {
     /// Spotify Client Secret
     #[clap(short, long)]
     secret: String,
+
+    /// Do not read or write the cached OAuth token; always prompt for login
+    #[clap(long)]
+    no_cache: bool,
+
+    /// Path to the OAuth token cache file (defaults to the OS cache directory)
+    #[clap(long)]
+    cache_path: Option<PathBuf>,
+
+    /// Maximum number of Spotify searches to run concurrently
+    #[clap(long, default_value_t = 10)]
+    concurrency: usize,
+
+    /// Target playlist name or Spotify URI (imported into it, creating it if
+    /// the name does not exist; defaults to a playlist named "Imported")
+    #[clap(long)]
+    playlist: Option<String>,
+
+    /// Run matching but skip playlist changes, emitting a match report instead
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Where to write the match report (defaults to stdout); JSON unless the
+    /// path ends in `.csv`
+    #[clap(long)]
+    report: Option<PathBuf>,
+
+    /// Import exactly the matched URIs from a previously written report,
+    /// bypassing search
+    #[clap(long)]
+    from_report: Option<PathBuf>,
+}
+
+impl Args {
+    /// Resolve the token cache location, honouring `--no-cache` and
+    /// `--cache-path`.
+    fn token_cache_path(&self) -> Option<PathBuf> {
+        if self.no_cache {
+            return None;
+        }
+        Some(self.cache_path.clone().unwrap_or_else(|| {
+            let mut path = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("."));
+            path.push("spotify-import");
+            path.push("token_cache.json");
+            path
+        }))
+    }
+}
+
+/// The structured fields lofty exposes for a local file, used both to build the
+/// Spotify search string and to score the candidates it returns.
+struct SearchQuery {
+    file: String,
+    title: String,
+    artist: String,
+    album: Option<String>,
+    duration: Option<Duration>,
 }
 
-struct SearchQuery(String);
+impl SearchQuery {
+    /// The free-text query sent to Spotify's search endpoint.
+    fn query(&self) -> String {
+        format!("{} - {}", self.title, self.artist)
+    }
+}
 
-impl TryFrom<Tag> for SearchQuery {
+impl TryFrom<(String, Tag, Option<Duration>)> for SearchQuery {
     type Error = ();
 
-    fn try_from(tag: Tag) -> Result<Self, Self::Error> {
+    fn try_from(
+        (file, tag, duration): (String, Tag, Option<Duration>),
+    ) -> Result<Self, Self::Error> {
         match (tag.title(), tag.artist()) {
-            (Some(title), Some(artist)) => Ok(SearchQuery(format!("{} - {}", title, artist))),
+            (Some(title), Some(artist)) => Ok(SearchQuery {
+                file,
+                title: title.to_string(),
+                artist: artist.to_string(),
+                album: tag.album().map(|album| album.to_string()),
+                duration,
+            }),
             _ => Err(()),
         }
     }
 }
 
+/// One row of the match report: which local file produced which Spotify match.
+#[derive(Debug, Serialize, Deserialize)]
+struct ReportEntry {
+    file: String,
+    query: String,
+    matched: bool,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    artist: Option<String>,
+    #[serde(default)]
+    uri: Option<String>,
+}
+
+/// The kinds of Spotify resource we can recognise from a tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpotifyKind {
+    Track,
+    Album,
+    Playlist,
+}
+
+impl SpotifyKind {
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "track" => Some(SpotifyKind::Track),
+            "album" => Some(SpotifyKind::Album),
+            "playlist" => Some(SpotifyKind::Playlist),
+            _ => None,
+        }
+    }
+}
+
+/// A Spotify resource parsed directly out of a tag, skipping fuzzy search.
+#[derive(Debug, Clone)]
+struct SpotifyRef {
+    kind: SpotifyKind,
+    id: String,
+}
+
+impl SpotifyRef {
+    /// Recognise a `spotify:...` URI or an `open.spotify.com/...` URL appearing
+    /// anywhere within a (possibly free-text) field, e.g. `great track
+    /// spotify:track:<id>`.
+    fn parse(value: &str) -> Option<Self> {
+        for token in value.split_whitespace() {
+            if let Some(start) = token.find("spotify:") {
+                if let Some(parsed) = Self::from_uri(&token[start..]) {
+                    return Some(parsed);
+                }
+            }
+            if token.contains("open.spotify.com/") {
+                if let Some(parsed) = Self::from_url(token) {
+                    return Some(parsed);
+                }
+            }
+        }
+        None
+    }
+
+    /// Parse `spotify:<kind>:<id>`.
+    fn from_uri(uri: &str) -> Option<Self> {
+        let mut parts = uri.split(':');
+        if parts.next()? != "spotify" {
+            return None;
+        }
+        let kind = SpotifyKind::parse(parts.next()?)?;
+        let id = strip_si(parts.next()?).to_string();
+        Some(SpotifyRef { kind, id })
+    }
+
+    /// Parse `https://open.spotify.com/<kind>/<id>?si=...`, tolerating the
+    /// locale-prefixed form Spotify now emits (`.../intl-de/track/<id>`).
+    fn from_url(url: &str) -> Option<Self> {
+        let rest = url.split("open.spotify.com/").nth(1)?;
+        let mut parts = rest.split('/');
+        let mut segment = parts.next()?;
+        if segment.starts_with("intl-") {
+            segment = parts.next()?;
+        }
+        let kind = SpotifyKind::parse(segment)?;
+        let id = strip_si(parts.next()?).to_string();
+        Some(SpotifyRef { kind, id })
+    }
+
+    /// Render this reference back as a canonical `spotify:<kind>:<id>` URI.
+    fn uri(&self) -> String {
+        let kind = match self.kind {
+            SpotifyKind::Track => "track",
+            SpotifyKind::Album => "album",
+            SpotifyKind::Playlist => "playlist",
+        };
+        format!("spotify:{}:{}", kind, self.id)
+    }
+}
+
+/// Drop the trailing `?si=...` (or any other) query suffix from an ID segment.
+fn strip_si(segment: &str) -> &str {
+    segment.split('?').next().unwrap_or(segment)
+}
+
 #[tokio::main]
 async fn main() {
     env_logger::init_from_env(
@@ -43,95 +241,570 @@ async fn main() {
     );
 
     let args = Args::parse();
-    let spotify = auth(&args.client_id, &args.secret).await;
+    let spotify = auth(&args).await;
 
-    let tags = collect_track_tags(&args.path);
-    info!("Found {} local tracks", tags.len());
+    // `--from-report` bypasses tag collection and matching entirely, importing
+    // exactly the matched URIs from a (possibly hand-edited) report.
+    if let Some(path) = &args.from_report {
+        let report = match read_report(path) {
+            Ok(report) => report,
+            Err(err) => {
+                error!("Failed to read report '{}': {}", path.display(), err);
+                return;
+            }
+        };
+        let track_ids = track_ids_from_report(&report);
+        import(&spotify, &args, &track_ids).await;
+        return;
+    }
 
-    let queries = tags
-        .into_iter()
-        .filter_map(|tag| SearchQuery::try_from(tag).ok())
-        .collect();
-    let track_ids = get_track_ids(queries, &spotify).await;
+    let tracks = collect_track_tags(&args.path);
+    info!("Found {} local tracks", tracks.len());
+
+    // Files whose tags already point at a Spotify resource skip the search
+    // stage entirely; everything else is matched by fuzzy search.
+    let mut refs = vec![];
+    let mut queries = vec![];
+    for (path, tag, duration) in tracks {
+        if let Some(spotify_ref) = spotify_ref_from_tag(&tag) {
+            refs.push((path, spotify_ref));
+        } else if let Ok(query) = SearchQuery::try_from((path, tag, duration)) {
+            queries.push(query);
+        }
+    }
+
+    let mut report = match_queries(queries, &spotify, args.concurrency).await;
+    for (path, spotify_ref) in refs {
+        for id in expand_spotify_ref(&spotify, &spotify_ref).await {
+            report.push(ReportEntry {
+                file: path.clone(),
+                query: spotify_ref.uri(),
+                matched: true,
+                title: None,
+                artist: None,
+                uri: Some(id.uri()),
+            });
+        }
+    }
+
+    let track_ids = track_ids_from_report(&report);
     info!("Found {} tracks in the Spotify library", track_ids.len());
 
-    let result = add_tracks_to_spotify(spotify, "Imported", &track_ids).await;
+    if args.dry_run {
+        if let Err(err) = write_report(&report, args.report.as_deref()) {
+            error!("Failed to write report: {}", err);
+        }
+        return;
+    }
+
+    import(&spotify, &args, &track_ids).await;
+}
+
+/// Create/resolve the target playlist and add the matched tracks, logging the
+/// outcome.
+async fn import(spotify: &AuthCodeSpotify, args: &Args, track_ids: &[TrackId]) {
+    let result = add_tracks_to_spotify(spotify, args.playlist.as_deref(), track_ids).await;
     match result {
         Ok(_) => info!("Successfully imported {} tracks", track_ids.len()),
         Err(_) => error!("Failed to import tracks"),
     }
 }
 
-async fn auth(id: &str, secret: &str) -> AuthCodeSpotify {
-    let creds = Credentials::new(id, secret);
+/// Serialise the match report to `path` (JSON, or CSV when the path ends in
+/// `.csv`), or to stdout as JSON when no path is given.
+fn write_report(report: &[ReportEntry], path: Option<&std::path::Path>) -> std::io::Result<()> {
+    match path {
+        Some(path) if path.extension().map(|e| e == "csv").unwrap_or(false) => {
+            let mut writer = csv::Writer::from_path(path)?;
+            for entry in report {
+                writer.serialize(entry)?;
+            }
+            writer.flush()
+        }
+        Some(path) => {
+            let json = serde_json::to_string_pretty(report)?;
+            std::fs::write(path, json)
+        }
+        None => {
+            let json = serde_json::to_string_pretty(report)?;
+            println!("{}", json);
+            Ok(())
+        }
+    }
+}
+
+/// Read back a match report written by [`write_report`], inferring CSV vs JSON
+/// from the file extension.
+fn read_report(path: &std::path::Path) -> std::io::Result<Vec<ReportEntry>> {
+    if path.extension().map(|e| e == "csv").unwrap_or(false) {
+        let mut reader = csv::Reader::from_path(path)?;
+        reader
+            .deserialize()
+            .collect::<Result<Vec<ReportEntry>, _>>()
+            .map_err(std::io::Error::other)
+    } else {
+        let data = std::fs::read_to_string(path)?;
+        serde_json::from_str(&data).map_err(std::io::Error::other)
+    }
+}
+
+async fn auth(args: &Args) -> AuthCodeSpotify {
+    let creds = Credentials::new(&args.client_id, &args.secret);
     let oauth = OAuth {
         redirect_uri: "http://localhost:8888/callback".to_string(),
         scopes: scopes!("playlist-modify-private"),
         ..Default::default()
     };
-    let mut spotify = AuthCodeSpotify::new(creds, oauth);
-    let url = spotify.get_authorize_url(false).unwrap();
 
-    info!("Obtaining the access token");
-    spotify.prompt_for_token(&url).await.unwrap();
+    let config = match args.token_cache_path() {
+        Some(cache_path) => Config {
+            token_cached: true,
+            token_refreshing: true,
+            cache_path,
+            ..Default::default()
+        },
+        None => Config::default(),
+    };
+    let spotify = AuthCodeSpotify::with_config(creds, oauth, config);
+
+    // Reuse a cached token when one is present and still usable (refreshing it
+    // if expired), otherwise fall back to the interactive login flow.
+    match spotify.read_token_cache(true).await {
+        Ok(Some(token)) => {
+            info!("Reusing cached access token");
+            *spotify.token.lock().await.unwrap() = Some(token);
+        }
+        _ => {
+            let url = spotify.get_authorize_url(false).unwrap();
+            info!("Obtaining the access token");
+            spotify.prompt_for_token(&url).await.unwrap();
+        }
+    }
 
     spotify
 }
 
-fn collect_track_tags(dir: &str) -> Vec<Tag> {
+fn collect_track_tags(dir: &str) -> Vec<(String, Tag, Option<Duration>)> {
     WalkDir::new(dir)
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
-        .filter_map(|e| lofty::read_from_path(e.path(), false).ok())
-        .filter_map(|file| {
+        .filter_map(|e| {
+            let path = e.path().to_string_lossy().into_owned();
+            lofty::read_from_path(e.path(), false)
+                .ok()
+                .map(|file| (path, file))
+        })
+        .filter_map(|(path, file)| {
+            let duration = Some(file.properties().duration());
             file.primary_tag()
                 .cloned()
                 .or_else(|| file.first_tag().cloned())
+                .map(|tag| (path, tag, duration))
         })
         .collect()
 }
 
-async fn get_track_ids(queries: Vec<SearchQuery>, spotify: &AuthCodeSpotify) -> Vec<TrackId> {
-    let mut track_ids = vec![];
-    for query in queries {
-        let result = spotify
-            .search(&query.0, &SearchType::Track, None, None, Some(1), None)
-            .await;
-        if let Ok(SearchResult::Tracks(track)) = result {
-            if let Some(id) = track.items.first().and_then(|t| t.id.clone()) {
-                track_ids.push(id);
-                
-                continue;
+/// Run a Spotify call, transparently retrying on rate-limit and transient
+/// network errors. A rate-limit response sleeps for the server-provided
+/// `Retry-After` duration (defaulting to ~5s when absent); other transient
+/// errors back off with capped exponential delay. Genuine failures (e.g. a
+/// 404 "not found") are returned unchanged so callers can fall through to
+/// their usual error handling.
+async fn with_retry<F, Fut, T>(mut call: F) -> ClientResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ClientResult<T>>,
+{
+    let mut hard_failures = 0u32;
+    loop {
+        match call().await {
+            Ok(value) => return Ok(value),
+            Err(err) => {
+                if let Some(retry_after) = rate_limit_retry_after(&err) {
+                    let secs = retry_after.unwrap_or(DEFAULT_RETRY_AFTER_SECS);
+                    warn!("Rate limited by Spotify, retrying in {}s", secs);
+                    tokio::time::sleep(Duration::from_secs(secs)).await;
+                    continue;
+                }
+                if is_transient(&err) && hard_failures < MAX_HARD_RETRIES {
+                    let backoff = 1u64 << hard_failures;
+                    hard_failures += 1;
+                    warn!("Transient error from Spotify, retrying in {}s", backoff);
+                    tokio::time::sleep(Duration::from_secs(backoff)).await;
+                    continue;
+                }
+                return Err(err);
             }
         }
+    }
+}
 
-        warn!("'{}' not found in the Spotify library", query.0);
+/// Returns `Some(retry_after_secs)` if the error is a rate-limit (HTTP 429),
+/// with the inner `Option` carrying the parsed `Retry-After` header if present.
+fn rate_limit_retry_after(err: &ClientError) -> Option<Option<u64>> {
+    match err {
+        ClientError::Http(http) => match http.as_ref() {
+            HttpError::StatusCode(response) if response.status().as_u16() == 429 => {
+                let seconds = response
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<u64>().ok());
+                Some(seconds)
+            }
+            _ => None,
+        },
+        _ => None,
     }
-    track_ids
+}
+
+/// Network-level failures are worth retrying with backoff; parse/auth errors
+/// are not.
+fn is_transient(err: &ClientError) -> bool {
+    matches!(err, ClientError::Http(http) if matches!(http.as_ref(), HttpError::Client(_)))
+}
+
+/// Pick the best-scoring candidate that clears [`MATCH_THRESHOLD`], if any.
+fn best_match<'a>(query: &SearchQuery, candidates: &'a [FullTrack]) -> Option<(&'a FullTrack, f64)> {
+    candidates
+        .iter()
+        .map(|track| (track, score_candidate(query, track)))
+        .filter(|(_, score)| *score >= MATCH_THRESHOLD)
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+}
+
+/// Score a Spotify candidate against the local file on a 0..=1 scale, combining
+/// fuzzy title/artist similarity with album match and track-duration closeness.
+fn score_candidate(query: &SearchQuery, track: &FullTrack) -> f64 {
+    let artists: Vec<&str> = track.artists.iter().map(|artist| artist.name.as_str()).collect();
+    let remote_duration = track.duration.num_milliseconds() as f64 / 1000.0;
+    score_fields(query, &track.name, &artists, &track.album.name, remote_duration)
+}
+
+/// Core scoring over plain fields so the heuristic can be unit-tested without
+/// constructing a full `FullTrack`. A candidate whose best artist similarity
+/// falls below [`MIN_ARTIST_SIMILARITY`] is vetoed outright (score `0.0`), so a
+/// same-title cover by a different artist can never match on title alone.
+fn score_fields(
+    query: &SearchQuery,
+    title: &str,
+    artists: &[&str],
+    album: &str,
+    remote_duration: f64,
+) -> f64 {
+    let title_sim = strsim::normalized_levenshtein(&normalize(&query.title), &normalize(title));
+    let artist_sim = artists
+        .iter()
+        .map(|artist| strsim::normalized_levenshtein(&normalize(&query.artist), &normalize(artist)))
+        .fold(0.0, f64::max);
+
+    if artist_sim < MIN_ARTIST_SIMILARITY {
+        return 0.0;
+    }
+
+    let mut score = 0.6 * title_sim + 0.3 * artist_sim;
+
+    if let Some(query_album) = &query.album {
+        score += 0.05 * strsim::normalized_levenshtein(&normalize(query_album), &normalize(album));
+    }
+
+    if let Some(duration) = query.duration {
+        let local = duration.as_secs_f64();
+        let diff = (local - remote_duration).abs();
+        // Full credit within 2s, decaying linearly to nothing by 30s apart.
+        let closeness = (1.0 - (diff - 2.0).max(0.0) / 28.0).clamp(0.0, 1.0);
+        score += 0.05 * closeness;
+    }
+
+    score
+}
+
+/// Lowercase and trim a string for case-insensitive similarity comparisons.
+fn normalize(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+/// Look for a Spotify URI/URL embedded in a file's tag (title, artist or
+/// comment field).
+fn spotify_ref_from_tag(tag: &Tag) -> Option<SpotifyRef> {
+    [tag.comment(), tag.title(), tag.artist()]
+        .into_iter()
+        .flatten()
+        .find_map(SpotifyRef::parse)
+}
+
+/// Turn a recognised Spotify reference into the track IDs it stands for,
+/// expanding albums and playlists through their paginated item lists.
+async fn expand_spotify_ref(spotify: &AuthCodeSpotify, spotify_ref: &SpotifyRef) -> Vec<TrackId> {
+    match spotify_ref.kind {
+        SpotifyKind::Track => TrackId::from_id(&spotify_ref.id).ok().into_iter().collect(),
+        SpotifyKind::Album => match AlbumId::from_id(&spotify_ref.id) {
+            Ok(album_id) => {
+                let mut ids = vec![];
+                let mut tracks = spotify.album_track(&album_id);
+                while let Ok(Some(track)) = with_retry(|| tracks.try_next()).await {
+                    if let Some(id) = track.id {
+                        ids.push(id);
+                    }
+                }
+                ids
+            }
+            Err(_) => {
+                warn!("Invalid Spotify album id '{}'", spotify_ref.id);
+                vec![]
+            }
+        },
+        SpotifyKind::Playlist => match PlaylistId::from_id(&spotify_ref.id) {
+            Ok(playlist_id) => existing_track_ids(spotify, &playlist_id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            Err(_) => {
+                warn!("Invalid Spotify playlist id '{}'", spotify_ref.id);
+                vec![]
+            }
+        },
+    }
+}
+
+/// Search for every query with up to `concurrency` requests in flight at once.
+/// Each search still goes through the rate-limit backoff. The returned report
+/// entries follow the order of the input queries; a miss produces an entry with
+/// `matched == false` (and a warning is logged per query).
+async fn match_queries(
+    queries: Vec<SearchQuery>,
+    spotify: &AuthCodeSpotify,
+    concurrency: usize,
+) -> Vec<ReportEntry> {
+    let mut results: Vec<(usize, ReportEntry)> = stream::iter(queries.into_iter().enumerate())
+        .map(|(index, query)| async move {
+            let text = query.query();
+            let result = with_retry(|| {
+                spotify.search(
+                    &text,
+                    &SearchType::Track,
+                    None,
+                    None,
+                    Some(CANDIDATES_PER_QUERY),
+                    None,
+                )
+            })
+            .await;
+            if let Ok(SearchResult::Tracks(page)) = result {
+                if let Some((track, _)) = best_match(&query, &page.items) {
+                    if let Some(id) = track.id.clone() {
+                        return (
+                            index,
+                            ReportEntry {
+                                file: query.file,
+                                query: text,
+                                matched: true,
+                                title: Some(track.name.clone()),
+                                artist: track.artists.first().map(|a| a.name.clone()),
+                                uri: Some(id.uri()),
+                            },
+                        );
+                    }
+                }
+            }
+
+            warn!("'{}' not found in the Spotify library", text);
+            (
+                index,
+                ReportEntry {
+                    file: query.file,
+                    query: text,
+                    matched: false,
+                    title: None,
+                    artist: None,
+                    uri: None,
+                },
+            )
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Collect the matched `TrackId`s out of a set of report entries.
+fn track_ids_from_report(report: &[ReportEntry]) -> Vec<TrackId> {
+    report
+        .iter()
+        .filter(|entry| entry.matched)
+        .filter_map(|entry| entry.uri.as_ref())
+        .filter_map(|uri| TrackId::from_id_or_uri(uri).ok())
+        .collect()
 }
 
 async fn add_tracks_to_spotify(
-    spotify: AuthCodeSpotify,
-    playlist_name: &str,
+    spotify: &AuthCodeSpotify,
+    target: Option<&str>,
     track_ids: &[TrackId],
 ) -> ClientResult<()> {
-    let user_id = spotify.current_user().await.expect("").id;
-    let playlist = spotify
-        .user_playlist_create(&user_id, playlist_name, Some(false), Some(false), None)
-        .await?;
+    let user_id = with_retry(|| spotify.current_user()).await?.id;
+    let playlist = resolve_playlist(spotify, &user_id, target).await?;
+
+    // Skip anything already in the playlist so re-running the import is
+    // idempotent rather than duplicating every track.
+    let existing = existing_track_ids(spotify, &playlist.id).await?;
+    let to_add: Vec<&TrackId> = track_ids
+        .iter()
+        .filter(|id| !existing.contains(*id))
+        .collect();
+    if to_add.len() < track_ids.len() {
+        info!(
+            "Skipping {} tracks already present in the playlist",
+            track_ids.len() - to_add.len()
+        );
+    }
 
     // A maximum of 100 items can be added in one request
     let mut position = 0;
-    for chunk in track_ids.chunks(100) {
-        let items: Vec<&dyn PlayableId> = chunk.iter().map(|id| id as &dyn PlayableId).collect();
-
-        spotify
-            .playlist_add_items(&playlist.id, items, Some(position))
-            .await?;
+    for chunk in to_add.chunks(100) {
+        with_retry(|| {
+            let items: Vec<&dyn PlayableId> =
+                chunk.iter().map(|id| *id as &dyn PlayableId).collect();
+            spotify.playlist_add_items(&playlist.id, items, Some(position))
+        })
+        .await?;
         position += 100;
         debug!("Imported 100 tracks at position {}", position)
     }
 
     Ok(())
 }
+
+/// Resolve `target` to a playlist: a Spotify URI/URL or an existing playlist
+/// name is reused, otherwise a new private playlist is created (named `target`,
+/// or "Imported" when no target was given).
+async fn resolve_playlist(
+    spotify: &AuthCodeSpotify,
+    user_id: &rspotify::model::UserId,
+    target: Option<&str>,
+) -> ClientResult<FullPlaylist> {
+    let name = match target {
+        None => "Imported",
+        Some(target) => {
+            let playlist_ref = SpotifyRef::parse(target)
+                .filter(|spotify_ref| spotify_ref.kind == SpotifyKind::Playlist);
+            if let Some(id) = playlist_ref.as_ref().and_then(|r| PlaylistId::from_id(&r.id).ok()) {
+                return with_retry(|| spotify.playlist(&id, None, None)).await;
+            }
+            target
+        }
+    };
+
+    if let Some(id) = find_playlist_by_name(spotify, name).await? {
+        return with_retry(|| spotify.playlist(&id, None, None)).await;
+    }
+
+    with_retry(|| spotify.user_playlist_create(user_id, name, Some(false), Some(false), None)).await
+}
+
+/// Find a playlist owned by the current user whose name matches `name`.
+async fn find_playlist_by_name(
+    spotify: &AuthCodeSpotify,
+    name: &str,
+) -> ClientResult<Option<PlaylistId>> {
+    let mut playlists = spotify.current_user_playlists();
+    while let Some(playlist) = with_retry(|| playlists.try_next()).await? {
+        if playlist.name == name {
+            return Ok(Some(playlist.id));
+        }
+    }
+    Ok(None)
+}
+
+/// Page through a playlist's current items and collect their track IDs.
+async fn existing_track_ids(
+    spotify: &AuthCodeSpotify,
+    playlist_id: &PlaylistId,
+) -> ClientResult<HashSet<TrackId>> {
+    let mut ids = HashSet::new();
+    let mut items = spotify.playlist_items(playlist_id, None, None);
+    while let Some(item) = with_retry(|| items.try_next()).await? {
+        if let Some(PlayableItem::Track(track)) = item.track {
+            if let Some(id) = track.id {
+                ids.insert(id);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(title: &str, artist: &str) -> SearchQuery {
+        SearchQuery {
+            file: String::new(),
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: None,
+            duration: None,
+        }
+    }
+
+    #[test]
+    fn spotify_kind_parses_known_kinds() {
+        assert_eq!(SpotifyKind::parse("track"), Some(SpotifyKind::Track));
+        assert_eq!(SpotifyKind::parse("album"), Some(SpotifyKind::Album));
+        assert_eq!(SpotifyKind::parse("playlist"), Some(SpotifyKind::Playlist));
+        assert_eq!(SpotifyKind::parse("artist"), None);
+    }
+
+    #[test]
+    fn parses_plain_uri() {
+        let parsed = SpotifyRef::parse("spotify:track:6rqhFgbbKwnb9MLmUQDhG6").unwrap();
+        assert_eq!(parsed.kind, SpotifyKind::Track);
+        assert_eq!(parsed.id, "6rqhFgbbKwnb9MLmUQDhG6");
+    }
+
+    #[test]
+    fn parses_uri_embedded_in_free_text() {
+        let parsed = SpotifyRef::parse("great track spotify:album:1234567890abcdefABCDEF").unwrap();
+        assert_eq!(parsed.kind, SpotifyKind::Album);
+        assert_eq!(parsed.id, "1234567890abcdefABCDEF");
+    }
+
+    #[test]
+    fn parses_url_and_strips_si_suffix() {
+        let parsed =
+            SpotifyRef::parse("https://open.spotify.com/track/6rqhFgbbKwnb9MLmUQDhG6?si=abc123")
+                .unwrap();
+        assert_eq!(parsed.kind, SpotifyKind::Track);
+        assert_eq!(parsed.id, "6rqhFgbbKwnb9MLmUQDhG6");
+    }
+
+    #[test]
+    fn parses_locale_prefixed_url() {
+        let parsed =
+            SpotifyRef::parse("https://open.spotify.com/intl-de/track/6rqhFgbbKwnb9MLmUQDhG6?si=x")
+                .unwrap();
+        assert_eq!(parsed.kind, SpotifyKind::Track);
+        assert_eq!(parsed.id, "6rqhFgbbKwnb9MLmUQDhG6");
+    }
+
+    #[test]
+    fn strip_si_drops_query_suffix() {
+        assert_eq!(strip_si("abc?si=def"), "abc");
+        assert_eq!(strip_si("abc"), "abc");
+    }
+
+    #[test]
+    fn wrong_artist_cannot_beat_correct_artist() {
+        let query = query("Bohemian Rhapsody", "Queen");
+        let right = score_fields(&query, "Bohemian Rhapsody", &["Queen"], "A Night at the Opera", 355.0);
+        let wrong = score_fields(&query, "Bohemian Rhapsody", &["Panic! At The Disco"], "Covers", 355.0);
+
+        assert!(right >= MATCH_THRESHOLD);
+        assert_eq!(wrong, 0.0, "a same-title cover by a different artist must be vetoed");
+        assert!(right > wrong);
+    }
+}